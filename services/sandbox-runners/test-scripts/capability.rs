@@ -0,0 +1,161 @@
+// Toolchain capability probing.
+//
+// Plugins compiled in the sandbox may rely on features that only exist on
+// some installed `rustc` (e.g. `#[track_caller]`, target-specific
+// intrinsics, nightly-only APIs). Rather than failing hard when a feature
+// is missing, a plugin should be able to ask "is X available?" and degrade
+// gracefully. This module probes the toolchain named by `RUSTC` by
+// compiling tiny snippets with `--emit=metadata` and records what worked.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// A single toolchain feature that can be probed for.
+pub struct Probe {
+    /// Capability name, e.g. `"track_caller"`. Used verbatim as the cfg name.
+    pub name: &'static str,
+    /// Minimal source that only compiles when the capability is present.
+    pub source: &'static str,
+    /// Extra rustc flags needed on top of `RUSTC_BOOTSTRAP=1` to exercise a
+    /// nightly-only capability (e.g. a `-Z` unstable-options flag). Most
+    /// nightly-gated probes need none beyond the bootstrap retry itself.
+    pub nightly_flags: &'static [&'static str],
+}
+
+/// Probe result for one capability.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    /// Not supported by this toolchain at all.
+    Unavailable,
+    /// Compiles as-is, no special flags required.
+    Stable,
+    /// Only compiles with `nightly_flags` applied (e.g. `-Z` unstable-options).
+    NightlyOnly,
+}
+
+pub const PROBES: &[Probe] = &[
+    Probe {
+        name: "track_caller",
+        source: "#[track_caller]\nfn probe() {}\nfn main() { probe(); }\n",
+        nightly_flags: &[],
+    },
+    Probe {
+        name: "target_feature_avx512",
+        source: "#[cfg(target_feature = \"avx512f\")]\nfn main() {}\n#[cfg(not(target_feature = \"avx512f\"))]\nfn main() { compile_error!(\"no avx512f\"); }\n",
+        nightly_flags: &[],
+    },
+    Probe {
+        // `#![feature(..)]` is rejected on a stable release channel, so this
+        // only compiles once the nightly feature gate is forced open (see
+        // `try_compile`'s `RUSTC_BOOTSTRAP=1` retry). Exercises the
+        // stable-vs-nightly-only distinction end to end.
+        name: "nightly_test_feature",
+        source: "#![feature(test)]\nfn main() {}\n",
+        nightly_flags: &[],
+    },
+];
+
+/// Probe every entry in `PROBES` against `rustc`, using `scratch_dir` as
+/// scratch space. Each probe gets its own subdirectory, removed afterward.
+pub fn probe_all(rustc: &Path, scratch_dir: &Path) -> io::Result<Vec<(&'static str, Availability)>> {
+    let mut results = Vec::with_capacity(PROBES.len());
+    for probe in PROBES {
+        results.push((probe.name, probe_one(rustc, scratch_dir, probe)?));
+    }
+    Ok(results)
+}
+
+fn probe_one(rustc: &Path, scratch_dir: &Path, probe: &Probe) -> io::Result<Availability> {
+    let dir = scratch_dir.join(format!("probe-{}", probe.name));
+    fs::create_dir_all(&dir)?;
+    let result = (|| {
+        let src_path = dir.join("probe.rs");
+        fs::write(&src_path, probe.source)?;
+
+        if try_compile(rustc, &src_path, &dir, &[], false)? {
+            return Ok(Availability::Stable);
+        }
+        // Retry with `RUSTC_BOOTSTRAP=1`, the standard escape hatch that
+        // unlocks `#![feature(..)]` and `-Z` flags on a stable-channel
+        // compiler, so a nightly-only capability can still be detected.
+        if try_compile(rustc, &src_path, &dir, probe.nightly_flags, true)? {
+            return Ok(Availability::NightlyOnly);
+        }
+        Ok(Availability::Unavailable)
+    })();
+
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+fn try_compile(
+    rustc: &Path,
+    src_path: &Path,
+    out_dir: &Path,
+    extra_flags: &[&str],
+    bootstrap: bool,
+) -> io::Result<bool> {
+    let mut command = Command::new(rustc);
+    command
+        .arg(src_path)
+        .arg("--emit=metadata")
+        .arg("--out-dir")
+        .arg(out_dir)
+        .args(extra_flags)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if bootstrap {
+        command.env("RUSTC_BOOTSTRAP", "1");
+    }
+    Ok(command.status()?.success())
+}
+
+/// Emit `cargo:rustc-cfg=<name>` lines for every available capability, for
+/// use from a crate's `build.rs`.
+pub fn emit_build_cfg(results: &[(&'static str, Availability)]) {
+    for (name, availability) in results {
+        if *availability != Availability::Unavailable {
+            println!("cargo:rustc-cfg={name}");
+        }
+    }
+}
+
+/// Render the probe results as a JSON capability manifest, to sit alongside
+/// `CASE_ID`/`EVIDENCE_UID` so a running plugin can branch on what's
+/// available instead of failing hard.
+pub fn to_manifest_json(results: &[(&'static str, Availability)]) -> String {
+    let mut out = String::from("{\n");
+    for (i, (name, availability)) in results.iter().enumerate() {
+        let value = match availability {
+            Availability::Unavailable => "\"unavailable\"",
+            Availability::Stable => "\"stable\"",
+            Availability::NightlyOnly => "\"nightly_only\"",
+        };
+        out.push_str(&format!("  \"{name}\": {value}"));
+        if i + 1 < results.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    out.push('}');
+    out
+}
+
+/// Probe the toolchain named by the `RUSTC` env var (falling back to
+/// `"rustc"`) using a scratch directory under `OUT_DIR` (falling back to
+/// `env::temp_dir()`).
+pub fn probe_from_env() -> io::Result<Vec<(&'static str, Availability)>> {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let scratch_base = env::var("OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir());
+    let scratch_dir = scratch_base.join("capability-probes");
+    fs::create_dir_all(&scratch_dir)?;
+
+    let results = probe_all(Path::new(&rustc), &scratch_dir)?;
+    let _ = fs::remove_dir_all(&scratch_dir);
+    Ok(results)
+}
@@ -0,0 +1,118 @@
+// Command-line front end for the sandbox test script.
+//
+// The sandbox contract used to be entirely implicit in the `CASE_ID` /
+// `EVIDENCE_UID` / `EVIDENCE_PATH` / `OUTPUT_DIR` environment variables,
+// which is fragile and hard to script. This gives the binary a proper
+// `Operation` to run plus flags that override the environment, so it can
+// be driven directly instead of only from inside the sandbox harness.
+
+use std::path::PathBuf;
+
+/// What the binary should do this invocation.
+pub enum Operation {
+    /// Run the sandbox test against the given evidence paths (or, if none
+    /// are given on the command line, whatever `EVIDENCE_PATH` names).
+    Run { evidence_paths: Vec<PathBuf> },
+    /// Run, then diff the result against the golden fixtures.
+    Verify,
+    /// Probe the toolchain and print the capability manifest.
+    ListCapabilities,
+    /// Print the binary's version and exit.
+    Version,
+    /// Read evidence bytes from standard input instead of a path.
+    Stdin,
+}
+
+/// `--case-id`/`--evidence-uid`/`--output-dir`/`--stdout`/`--pipe-to`
+/// overrides for the environment and output sink.
+#[derive(Default)]
+pub struct Overrides {
+    pub case_id: Option<String>,
+    pub evidence_uid: Option<String>,
+    pub output_dir: Option<PathBuf>,
+    /// Stream plugin output to stdout instead of `OUTPUT_DIR`.
+    pub stdout: bool,
+    /// Stream plugin output into the stdin of a spawned program instead of
+    /// `OUTPUT_DIR`/stdout. Takes priority over `--stdout`.
+    pub pipe_to: Option<String>,
+}
+
+/// A command line that couldn't be parsed, with the reason why.
+pub struct InvalidInput(pub String);
+
+/// Parse `argv[1..]` into an `Operation` plus any environment overrides.
+///
+/// Usage: `<mode> [evidence_path...] [--case-id ID] [--evidence-uid UID]
+/// [--output-dir DIR] [--stdout] [--pipe-to PROGRAM]`, where `<mode>` is one
+/// of `run` (the default when omitted), `verify`, `list-capabilities`,
+/// `version`, or `stdin`. Only `run` takes evidence path positionals; every
+/// other mode rejects them as invalid input.
+const MODES: &[&str] = &["run", "verify", "list-capabilities", "version", "stdin"];
+
+pub fn parse_args(args: &[String]) -> Result<(Operation, Overrides), InvalidInput> {
+    let mut args = args.iter().peekable();
+    let mode = match args.peek() {
+        Some(first) if MODES.contains(&first.as_str()) => args.next().unwrap().as_str(),
+        _ => "run",
+    };
+
+    let mut overrides = Overrides::default();
+    let mut evidence_paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--case-id" => overrides.case_id = Some(take_value(&mut args, "--case-id")?),
+            "--evidence-uid" => {
+                overrides.evidence_uid = Some(take_value(&mut args, "--evidence-uid")?)
+            }
+            "--output-dir" => {
+                overrides.output_dir = Some(PathBuf::from(take_value(&mut args, "--output-dir")?))
+            }
+            "--stdout" => overrides.stdout = true,
+            "--pipe-to" => overrides.pipe_to = Some(take_value(&mut args, "--pipe-to")?),
+            flag if flag.starts_with("--") => {
+                return Err(InvalidInput(format!("unknown flag: {flag}")));
+            }
+            path => evidence_paths.push(PathBuf::from(path)),
+        }
+    }
+
+    let operation = match mode {
+        "run" => Operation::Run { evidence_paths },
+        "verify" => reject_positionals(evidence_paths, "verify", Operation::Verify)?,
+        "list-capabilities" => {
+            reject_positionals(evidence_paths, "list-capabilities", Operation::ListCapabilities)?
+        }
+        "version" => reject_positionals(evidence_paths, "version", Operation::Version)?,
+        "stdin" => reject_positionals(evidence_paths, "stdin", Operation::Stdin)?,
+        _ => unreachable!("mode is always \"run\" or a member of MODES"),
+    };
+
+    Ok((operation, overrides))
+}
+
+/// `run` is the only mode that takes evidence path positionals; every other
+/// mode treats a stray positional as invalid input rather than silently
+/// dropping it.
+fn reject_positionals(
+    evidence_paths: Vec<PathBuf>,
+    mode: &str,
+    operation: Operation,
+) -> Result<Operation, InvalidInput> {
+    if evidence_paths.is_empty() {
+        Ok(operation)
+    } else {
+        Err(InvalidInput(format!(
+            "{mode} does not take positional arguments, got: {evidence_paths:?}"
+        )))
+    }
+}
+
+fn take_value(
+    args: &mut std::iter::Peekable<std::slice::Iter<String>>,
+    flag: &str,
+) -> Result<String, InvalidInput> {
+    args.next()
+        .cloned()
+        .ok_or_else(|| InvalidInput(format!("{flag} requires a value")))
+}
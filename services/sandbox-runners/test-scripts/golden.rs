@@ -0,0 +1,299 @@
+// Golden-output regression harness for sandbox plugins.
+//
+// Modeled on the usual source/target fixture layout: a fixture directory
+// holds `evidence/` (input for the plugin) and `expected/` (the output
+// tree the plugin must reproduce byte-for-byte). The harness runs the
+// plugin with the usual `CASE_ID`/`EVIDENCE_UID`/`EVIDENCE_PATH`/
+// `OUTPUT_DIR` env set, then diffs everything the plugin actually wrote
+// under `OUTPUT_DIR` against `expected/`.
+//
+// A fixture may carry a `fixture.conf` file at its root with `key = value`
+// lines that override the sandbox settings for that one case, plus a `skip`
+// list of filenames that should not be compared directly (e.g. outputs that
+// are expected to be nondeterministic, like timestamps).
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const DIFF_CONTEXT: usize = 3;
+
+/// Per-fixture overrides read from `fixture.conf`.
+#[derive(Default)]
+pub struct FixtureConfig {
+    pub case_id: Option<String>,
+    pub evidence_uid: Option<String>,
+    /// Filenames (relative to `expected/`) excluded from direct comparison.
+    pub skip: HashSet<String>,
+}
+
+impl FixtureConfig {
+    fn load(fixture_dir: &Path) -> FixtureConfig {
+        let mut config = FixtureConfig::default();
+        let path = fixture_dir.join("fixture.conf");
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return config;
+        };
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "case_id" => config.case_id = Some(value.to_string()),
+                "evidence_uid" => config.evidence_uid = Some(value.to_string()),
+                "skip" => config
+                    .skip
+                    .extend(value.split(',').map(|s| s.trim().to_string())),
+                _ => {}
+            }
+        }
+        config
+    }
+}
+
+/// Run `plugin_bin` against the fixture at `fixture_dir` and compare
+/// everything it writes under a fresh output directory against
+/// `fixture_dir/expected`. Returns `Ok(())` on an exact match (modulo the
+/// skip list), or `Err(diff)` with a unified diff of the first mismatch.
+pub fn run_fixture(plugin_bin: &Path, fixture_dir: &Path) -> Result<(), String> {
+    let config = FixtureConfig::load(fixture_dir);
+    let evidence_dir = fixture_dir.join("evidence");
+    let expected_dir = fixture_dir.join("expected");
+
+    let actual_dir = fixture_dir.join(".golden-actual");
+    let _ = fs::remove_dir_all(&actual_dir);
+    fs::create_dir_all(&actual_dir).map_err(|e| e.to_string())?;
+
+    let status = Command::new(plugin_bin)
+        .env(
+            "CASE_ID",
+            config.case_id.as_deref().unwrap_or("GOLDEN_TEST_CASE"),
+        )
+        .env(
+            "EVIDENCE_UID",
+            config.evidence_uid.as_deref().unwrap_or("GOLDEN_TEST_EVIDENCE"),
+        )
+        .env("EVIDENCE_PATH", &evidence_dir)
+        .env("OUTPUT_DIR", &actual_dir)
+        .status()
+        .map_err(|e| format!("failed to run plugin {plugin_bin:?}: {e}"))?;
+
+    if !status.success() {
+        return Err(format!("plugin exited with {status}"));
+    }
+
+    let result = compare_trees(&expected_dir, &actual_dir, &config.skip);
+    let _ = fs::remove_dir_all(&actual_dir);
+    result
+}
+
+fn compare_trees(expected_dir: &Path, actual_dir: &Path, skip: &HashSet<String>) -> Result<(), String> {
+    let mut expected_rels: HashSet<String> = HashSet::new();
+
+    for entry in walk_files(expected_dir) {
+        let rel = entry
+            .strip_prefix(expected_dir)
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        expected_rels.insert(rel.clone());
+        if skip.contains(&rel) {
+            continue;
+        }
+
+        let actual_path = actual_dir.join(&rel);
+        let expected_contents =
+            fs::read_to_string(&entry).map_err(|e| format!("reading expected {rel}: {e}"))?;
+        let actual_contents = fs::read_to_string(&actual_path)
+            .map_err(|e| format!("{rel}: not produced by plugin ({e})"))?;
+
+        if expected_contents != actual_contents {
+            let diff = unified_diff(&expected_contents, &actual_contents, DIFF_CONTEXT);
+            return Err(format!("mismatch in {rel}:\n{diff}"));
+        }
+    }
+
+    // The request asks for everything emitted under `OUTPUT_DIR` to be
+    // compared, not just the files `expected/` happens to name — a rogue
+    // extra artifact is as much a regression as a missing or wrong one.
+    for entry in walk_files(actual_dir) {
+        let rel = entry
+            .strip_prefix(actual_dir)
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        if skip.contains(&rel) || expected_rels.contains(&rel) {
+            continue;
+        }
+        return Err(format!("unexpected file produced by plugin: {rel}"));
+    }
+
+    Ok(())
+}
+
+fn walk_files(dir: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    if !dir.is_dir() {
+        return out;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return out;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            out.extend(walk_files(&path));
+        } else {
+            out.push(path);
+        }
+    }
+    out
+}
+
+/// A minimal unified-diff renderer with `context` lines of surrounding
+/// context, in the style of `diff -U<context>`.
+fn unified_diff(expected: &str, actual: &str, context: usize) -> String {
+    let old_lines: Vec<&str> = expected.lines().collect();
+    let new_lines: Vec<&str> = actual.lines().collect();
+    let ops = lcs_diff(&old_lines, &new_lines);
+
+    let mut out = String::new();
+    out.push_str("--- expected\n+++ actual\n");
+
+    let mut i = 0;
+    while i < ops.len() {
+        if matches!(ops[i], DiffOp::Equal(_)) {
+            i += 1;
+            continue;
+        }
+        // Found a change; grow a hunk with `context` lines of padding.
+        let hunk_start = i.saturating_sub(context);
+        let mut hunk_end = i;
+        while hunk_end < ops.len() {
+            if matches!(ops[hunk_end], DiffOp::Equal(_)) {
+                // Peek ahead: stop growing once we've covered `context`
+                // trailing equal lines and the next op (if any) is also equal.
+                let mut run = 0;
+                let mut j = hunk_end;
+                while j < ops.len() && matches!(ops[j], DiffOp::Equal(_)) {
+                    run += 1;
+                    j += 1;
+                }
+                if run > context {
+                    hunk_end += context;
+                    break;
+                }
+            }
+            hunk_end += 1;
+        }
+        let hunk_end = hunk_end.min(ops.len());
+
+        for op in &ops[hunk_start..hunk_end] {
+            match op {
+                DiffOp::Equal(line) => out.push_str(&format!(" {line}\n")),
+                DiffOp::Removed(line) => out.push_str(&format!("-{line}\n")),
+                DiffOp::Added(line) => out.push_str(&format!("+{line}\n")),
+            }
+        }
+        i = hunk_end;
+    }
+
+    out
+}
+
+enum DiffOp<'a> {
+    Equal(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Classic LCS-table diff. Fine for fixture-sized files; not meant for
+/// megabyte inputs.
+fn lcs_diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffOp<'a>> {
+    let (m, n) = (old.len(), new.len());
+    let mut table = vec![vec![0usize; n + 1]; m + 1];
+    for i in (0..m).rev() {
+        for j in (0..n).rev() {
+            table[i][j] = if old[i] == new[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < m && j < n {
+        if old[i] == new[j] {
+            ops.push(DiffOp::Equal(old[i]));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            ops.push(DiffOp::Removed(old[i]));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Added(new[j]));
+            j += 1;
+        }
+    }
+    while i < m {
+        ops.push(DiffOp::Removed(old[i]));
+        i += 1;
+    }
+    while j < n {
+        ops.push(DiffOp::Added(new[j]));
+        j += 1;
+    }
+    ops
+}
+
+/// Entry point for a standalone harness invocation: `golden <plugin-bin>
+/// <fixtures-dir>`, running every immediate subdirectory of `fixtures-dir`
+/// as a fixture.
+///
+/// A missing/unreadable `fixtures_dir`, or one with no fixture
+/// subdirectories, is an error rather than a vacuous pass — a regression
+/// harness that "checked nothing" must not report green.
+pub fn run_all(plugin_bin: &Path, fixtures_dir: &Path) -> Result<(), Vec<(String, String)>> {
+    let mut failures = Vec::new();
+
+    let entries = fs::read_dir(fixtures_dir).map_err(|e| {
+        vec![(
+            "<harness>".to_string(),
+            format!("fixtures dir {fixtures_dir:?} not readable: {e}"),
+        )]
+    })?;
+
+    let mut fixture_count = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        fixture_count += 1;
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if let Err(diff) = run_fixture(plugin_bin, &path) {
+            failures.push((name, diff));
+        }
+    }
+
+    if fixture_count == 0 {
+        failures.push((
+            "<harness>".to_string(),
+            format!("no fixtures found under {fixtures_dir:?}"),
+        ));
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
@@ -0,0 +1,194 @@
+// Shared durable-write path for sandbox plugin output.
+//
+// Evidence artifacts feed a forensic chain of custody, so a half-written
+// file after a crash or power loss is worse than no file at all. Every
+// plugin should go through `write_to_file` (or the lower-level `Sink`)
+// instead of writing directly, since it guarantees the final path is
+// either fully present or absent.
+//
+// Artifacts like timelines, carved files, and hex dumps can be large, so
+// writes go through a `BufWriter` rather than buffering the whole thing
+// into memory first, and the sink itself is selectable: a file under
+// `OUTPUT_DIR` (the default, atomic), stdout, or a caller-provided writer.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Anything that can durably persist itself under a sandbox output directory.
+pub trait DiskWriteable {
+    /// Serialize `self` to the bytes that should land on disk.
+    fn as_bytes(&self) -> &[u8];
+}
+
+impl DiskWriteable for str {
+    fn as_bytes(&self) -> &[u8] {
+        str::as_bytes(self)
+    }
+}
+
+impl DiskWriteable for Vec<u8> {
+    fn as_bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// Where a plugin's output should land.
+pub enum Sink {
+    /// Atomically write `<output_dir>/<filename>` (the default).
+    File(PathBuf),
+    /// Stream straight to stdout. No atomicity guarantee applies: there is
+    /// no "final path" to rename into place.
+    Stdout,
+    /// Stream into a caller-provided writer, e.g. to pipeline into another
+    /// process. No atomicity guarantee applies.
+    Writer(Box<dyn Write>),
+}
+
+/// Write `data` to `output_dir/filename` such that, even if the process is
+/// killed mid-write, the final path either holds the complete contents or
+/// does not exist at all.
+///
+/// The dance: stream the full contents into a `.tmp` sibling through a
+/// `BufWriter`, `flush()` and `sync_all()` it, `rename()` it over the final
+/// path, then `fsync` the parent directory so the rename itself survives a
+/// crash.
+pub fn write_to_file<D: DiskWriteable + ?Sized>(
+    output_dir: &Path,
+    filename: &str,
+    data: &D,
+) -> io::Result<()> {
+    write_to_sink(Sink::File(output_dir.to_path_buf()), filename, data)
+}
+
+/// Like [`write_to_file`], but the destination is selectable. `filename` is
+/// only used by the `Sink::File` case.
+pub fn write_to_sink<D: DiskWriteable + ?Sized>(
+    sink: Sink,
+    filename: &str,
+    data: &D,
+) -> io::Result<()> {
+    match sink {
+        Sink::File(output_dir) => write_atomic_file(&output_dir, filename, data),
+        Sink::Stdout => write_streamed(&mut BufWriter::new(io::stdout()), data),
+        Sink::Writer(writer) => write_streamed(&mut BufWriter::new(writer), data),
+    }
+}
+
+/// Stream `data` into any `W: Write`, flushing once the full contents have
+/// been written. This is the generic write path every sink goes through.
+pub fn write_streamed<D: DiskWriteable + ?Sized, W: Write>(
+    writer: &mut W,
+    data: &D,
+) -> io::Result<()> {
+    writer.write_all(data.as_bytes())?;
+    writer.flush()
+}
+
+fn write_atomic_file<D: DiskWriteable + ?Sized>(
+    output_dir: &Path,
+    filename: &str,
+    data: &D,
+) -> io::Result<()> {
+    let final_path = output_dir.join(filename);
+    let tmp_path = output_dir.join(format!("{filename}.tmp"));
+
+    {
+        let tmp_file = File::create(&tmp_path)?;
+        let mut buffered = BufWriter::new(tmp_file);
+        buffered.write_all(data.as_bytes())?;
+        buffered.flush()?;
+        buffered
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+    }
+
+    rename_replace(&tmp_path, &final_path)?;
+    sync_parent_dir(output_dir)?;
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn rename_replace(tmp_path: &Path, final_path: &Path) -> io::Result<()> {
+    fs::rename(tmp_path, final_path)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn sync_parent_dir(output_dir: &Path) -> io::Result<()> {
+    // Directory fsync has no Windows equivalent; FlushFileBuffers on the
+    // file handle (done via ReplaceFile below) is the closest analogue there.
+    File::open(output_dir)?.sync_all()
+}
+
+#[cfg(target_os = "windows")]
+fn rename_replace(tmp_path: &Path, final_path: &Path) -> io::Result<()> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::AsRawHandle;
+    use std::ptr;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn ReplaceFileW(
+            lpreplacedfilename: *const u16,
+            lpreplacementfilename: *const u16,
+            lpbackupfilename: *const u16,
+            dwreplaceflags: u32,
+            lpexclude: *mut std::ffi::c_void,
+            lpreserved: *mut std::ffi::c_void,
+        ) -> i32;
+        fn MoveFileExW(
+            lpexistingfilename: *const u16,
+            lpnewfilename: *const u16,
+            dwflags: u32,
+        ) -> i32;
+        fn FlushFileBuffers(hfile: *mut std::ffi::c_void) -> i32;
+    }
+
+    const MOVEFILE_REPLACE_EXISTING: u32 = 0x1;
+
+    fn wide(path: &Path) -> Vec<u16> {
+        OsStr::new(path)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    // Ensure the temp file's buffers are flushed to the OS before the move.
+    let tmp_handle = File::open(tmp_path)?;
+    unsafe {
+        FlushFileBuffers(tmp_handle.as_raw_handle() as *mut _);
+    }
+
+    let existing = wide(final_path);
+    let replacement = wide(tmp_path);
+
+    let ok = if final_path.exists() {
+        unsafe {
+            ReplaceFileW(
+                existing.as_ptr(),
+                replacement.as_ptr(),
+                ptr::null(),
+                0,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        }
+    } else {
+        unsafe { MoveFileExW(replacement.as_ptr(), existing.as_ptr(), MOVEFILE_REPLACE_EXISTING) }
+    };
+
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn sync_parent_dir(_output_dir: &Path) -> io::Result<()> {
+    // No directory-fsync equivalent on Windows; the rename is already
+    // durable once ReplaceFile/MoveFileEx returns and the handle is flushed.
+    Ok(())
+}
@@ -1,49 +1,175 @@
 // Test script for Rust sandbox
 // Verifies environment variables and basic functionality
 
+mod capability;
+mod cli;
+mod golden;
+mod output;
+
 use std::env;
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+use cli::{InvalidInput, Operation, Overrides};
+use output::{write_to_file, write_to_sink, Sink};
+
+const VERSION: &str = "0.1.0";
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (operation, overrides) = match cli::parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(InvalidInput(reason)) => {
+            eprintln!("error: invalid input: {reason}");
+            process::exit(2);
+        }
+    };
+
+    match operation {
+        Operation::Version => println!("datamortem sandbox test-rust {VERSION}"),
+        Operation::ListCapabilities => list_capabilities(&overrides),
+        Operation::Run { evidence_paths } => {
+            let evidence_path = evidence_paths
+                .into_iter()
+                .next()
+                .or_else(|| env::var("EVIDENCE_PATH").ok().map(PathBuf::from));
+            run_sandbox_test(&overrides, evidence_path.as_deref(), None);
+        }
+        Operation::Stdin => {
+            let mut evidence = Vec::new();
+            if let Err(e) = std::io::stdin().read_to_end(&mut evidence) {
+                eprintln!("error: failed to read stdin: {e}");
+                process::exit(1);
+            }
+            run_sandbox_test(&overrides, None, Some(&evidence));
+        }
+        Operation::Verify => verify(),
+    }
+}
+
+fn list_capabilities(overrides: &Overrides) {
+    match capability::probe_from_env() {
+        Ok(results) => {
+            let manifest = capability::to_manifest_json(&results);
+            // Surfaced three ways: a JSON manifest for inline inspection,
+            // `cargo:rustc-cfg=` lines for a crate's `build.rs`, and a
+            // `capabilities.json` under `OUTPUT_DIR` (alongside
+            // `CASE_ID`/`EVIDENCE_UID`) so a separately-running plugin,
+            // not just this process, can read it to branch on features.
+            println!("{manifest}");
+            capability::emit_build_cfg(&results);
+
+            let output_dir = overrides
+                .output_dir
+                .clone()
+                .or_else(|| env::var("OUTPUT_DIR").ok().map(PathBuf::from))
+                .or_else(|| env::var("OUT_DIR").ok().map(PathBuf::from));
+            if let Some(dir) = output_dir {
+                if let Err(e) = write_to_file(&dir, "capabilities.json", manifest.as_str()) {
+                    eprintln!("warning: failed to write capabilities.json: {e}");
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("error: capability probing failed: {e}");
+            process::exit(1);
+        }
+    }
+}
+
+fn verify() {
+    let exe = env::current_exe().unwrap_or_else(|_| PathBuf::from("test_rust"));
+    let fixtures_dir = Path::new("fixtures");
+    match golden::run_all(&exe, fixtures_dir) {
+        Ok(()) => println!("all fixtures passed"),
+        Err(failures) => {
+            for (name, diff) in &failures {
+                eprintln!("FAIL {name}\n{diff}");
+            }
+            process::exit(1);
+        }
+    }
+}
+
+/// The original env-var-driven sandbox test, now reachable from the `run`
+/// and `stdin` CLI modes as well as bare invocation (no args) for backward
+/// compatibility with the sandbox harness.
+fn run_sandbox_test(overrides: &Overrides, evidence_path: Option<&Path>, stdin_evidence: Option<&[u8]>) {
     println!("=== Rust Sandbox Test ===");
-    println!("Rust version: {}", env!("CARGO_PKG_RUST_VERSION", "unknown"));
     println!();
 
-    // Test environment variables
     println!("=== Environment Variables ===");
-    let case_id = env::var("CASE_ID").unwrap_or_else(|_| "NOT_SET".to_string());
-    let evidence_uid = env::var("EVIDENCE_UID").unwrap_or_else(|_| "NOT_SET".to_string());
-    let evidence_path = env::var("EVIDENCE_PATH").unwrap_or_else(|_| "NOT_SET".to_string());
-    let output_dir = env::var("OUTPUT_DIR").unwrap_or_else(|_| "NOT_SET".to_string());
+    let case_id = overrides
+        .case_id
+        .clone()
+        .unwrap_or_else(|| env::var("CASE_ID").unwrap_or_else(|_| "NOT_SET".to_string()));
+    let evidence_uid = overrides.evidence_uid.clone().unwrap_or_else(|| {
+        env::var("EVIDENCE_UID").unwrap_or_else(|_| "NOT_SET".to_string())
+    });
+    let evidence_path_display = match (evidence_path, stdin_evidence) {
+        (_, Some(bytes)) => format!("<stdin, {} bytes>", bytes.len()),
+        (Some(path), None) => path.display().to_string(),
+        (None, None) => env::var("EVIDENCE_PATH").unwrap_or_else(|_| "NOT_SET".to_string()),
+    };
+    let output_dir = overrides.output_dir.clone().unwrap_or_else(|| {
+        PathBuf::from(env::var("OUTPUT_DIR").unwrap_or_else(|_| "NOT_SET".to_string()))
+    });
 
     println!("CASE_ID: {}", case_id);
     println!("EVIDENCE_UID: {}", evidence_uid);
-    println!("EVIDENCE_PATH: {}", evidence_path);
-    println!("OUTPUT_DIR: {}", output_dir);
+    println!("EVIDENCE_PATH: {}", evidence_path_display);
+    println!("OUTPUT_DIR: {}", output_dir.display());
     println!();
 
     // Test output directory write
-    if output_dir != "NOT_SET" {
-        let output_path = Path::new(&output_dir).join("test_output_rust.txt");
-        match File::create(&output_path) {
-            Ok(mut file) => {
-                let content = format!(
-                    "Test output from Rust sandbox\nCase ID: {}\nEvidence UID: {}\n",
-                    case_id, evidence_uid
-                );
-                match file.write_all(content.as_bytes()) {
-                    Ok(_) => println!("✓ Output file written: {:?}", output_path),
+    let filename = "test_output_rust.txt";
+    let content = format!(
+        "Test output from Rust sandbox\nCase ID: {}\nEvidence UID: {}\n",
+        case_id, evidence_uid
+    );
+    if let Some(program) = &overrides.pipe_to {
+        // Stream straight into another process's stdin, e.g. to pipeline
+        // the plugin's output into a downstream analysis tool instead of
+        // landing it on disk.
+        println!("--- {filename} (piped to {program}) ---");
+        match Command::new(program).stdin(Stdio::piped()).spawn() {
+            Ok(mut child) => {
+                let stdin = child.stdin.take().expect("piped stdin");
+                match write_to_sink(Sink::Writer(Box::new(stdin)), filename, content.as_str()) {
+                    Ok(_) => {
+                        let _ = child.wait();
+                        println!("--- end {filename} ---");
+                    }
                     Err(e) => println!("✗ Output write failed: {}", e),
                 }
             }
-            Err(e) => println!("✗ Output file creation failed: {}", e),
+            Err(e) => println!("✗ failed to spawn {program}: {e}"),
+        }
+    } else if overrides.stdout {
+        println!("--- {filename} (stdout sink) ---");
+        match write_to_sink(Sink::Stdout, filename, content.as_str()) {
+            Ok(_) => println!("--- end {filename} ---"),
+            Err(e) => println!("✗ Output write failed: {}", e),
+        }
+    } else if output_dir.as_os_str() != "NOT_SET" {
+        match write_to_file(&output_dir, filename, content.as_str()) {
+            Ok(_) => println!("✓ Output file written: {:?}", output_dir.join(filename)),
+            Err(e) => println!("✗ Output write failed: {}", e),
         }
     } else {
         println!("⚠ OUTPUT_DIR not set, skipping file write test");
     }
 
+    println!();
+
+    // Test toolchain capability probing
+    println!("=== Toolchain Capabilities ===");
+    match capability::probe_from_env() {
+        Ok(results) => println!("{}", capability::to_manifest_json(&results)),
+        Err(e) => println!("✗ Capability probing failed: {}", e),
+    }
+
     println!();
     println!("=== Test Complete ===");
     println!("Exit code: 0");